@@ -0,0 +1,21 @@
+//! Runs the built binary for the integration tests.
+
+use std::process::{Command, Output};
+
+/// Run claude-diagnose from the crate root
+pub fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_claude-diagnose"))
+        .args(args)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        // Keep a developer's own config, rules and history out of the run
+        .env("XDG_CONFIG_HOME", env!("CARGO_TARGET_TMPDIR"))
+        .env("XDG_STATE_HOME", env!("CARGO_TARGET_TMPDIR"))
+        .output()
+        .expect("failed to run claude-diagnose")
+}
+
+/// Exit code of a run
+#[allow(dead_code)]
+pub fn code(args: &[&str]) -> i32 {
+    run(args).status.code().expect("killed by a signal")
+}
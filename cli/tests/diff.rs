@@ -0,0 +1,103 @@
+//! Process pairing in `diff`: session id first, then working directory, then PID.
+
+mod common;
+
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+
+fn claude_diagnose(args: &[&str]) -> Value {
+    let output = common::run(args);
+    assert!(output.status.success(), "claude-diagnose {:?} failed", args);
+    serde_json::from_slice(&output.stdout).expect("invalid JSON output")
+}
+
+/// A replayed one-process report with the given PID, session id and cwd
+fn report(dir: &Path, name: &str, pid: u32, session: &str, cwd: &str) -> PathBuf {
+    let mut report = claude_diagnose(&[
+        "--from-lsof",
+        "tests/fixtures/rules/lsof-normal.txt",
+        "--json",
+    ]);
+    let proc = &mut report["processes"][0];
+    proc["pid"] = json!(pid);
+    proc["session"]["session_id"] = json!(session);
+    proc["session"]["cwd"] = json!(cwd);
+    let path = dir.join(name);
+    std::fs::write(&path, report.to_string()).unwrap();
+    path
+}
+
+fn diff(name: &str, before: [(u32, &str, &str); 2], after: [(u32, &str, &str); 2]) -> Vec<Value> {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    std::fs::create_dir_all(&dir).unwrap();
+    let merge = |label: &str, procs: [(u32, &str, &str); 2]| {
+        let reports: Vec<Value> = procs
+            .iter()
+            .enumerate()
+            .map(|(i, (pid, session, cwd))| {
+                let path = report(&dir, &format!("{}-{}.json", label, i), *pid, session, cwd);
+                serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+            })
+            .collect();
+        let mut merged = reports[0].clone();
+        merged["processes"] = json!([reports[0]["processes"][0], reports[1]["processes"][0]]);
+        let path = dir.join(format!("{}.json", label));
+        std::fs::write(&path, merged.to_string()).unwrap();
+        path
+    };
+    let (before, after) = (merge("before", before), merge("after", after));
+    let diff = claude_diagnose(&[
+        "diff",
+        before.to_str().unwrap(),
+        after.to_str().unwrap(),
+        "--json",
+    ]);
+    diff["processes"].as_array().unwrap().clone()
+}
+
+fn pairing(processes: &[Value]) -> Vec<(Value, Value, Value)> {
+    processes
+        .iter()
+        .map(|p| {
+            (
+                p["pid_before"].clone(),
+                p["pid_after"].clone(),
+                p["matched_on"].clone(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn restarted_sessions_pair_by_session_over_pid() {
+    // PID 100 was reused by session b after session a restarted as PID 300
+    let processes = diff(
+        "diff-session",
+        [(100, "a", "/work/a"), (200, "b", "/work/b")],
+        [(100, "b", "/work/b"), (300, "a", "/work/a")],
+    );
+    assert_eq!(
+        pairing(&processes),
+        vec![
+            (json!(200), json!(100), json!("session")),
+            (json!(100), json!(300), json!("session")),
+        ]
+    );
+}
+
+#[test]
+fn a_reused_pid_is_not_paired_with_another_session() {
+    let processes = diff(
+        "diff-reused-pid",
+        [(100, "a", "/work/a"), (200, "b", "/work/b")],
+        [(100, "c", "/work/c"), (200, "b", "/work/b")],
+    );
+    assert_eq!(
+        pairing(&processes),
+        vec![
+            (Value::Null, json!(100), Value::Null),
+            (json!(200), json!(200), json!("session")),
+            (json!(100), Value::Null, Value::Null),
+        ]
+    );
+}
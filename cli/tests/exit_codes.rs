@@ -0,0 +1,76 @@
+//! Exit codes of replayed reports: 0 healthy, 1 warnings, 2 critical, 3 probe failure.
+
+mod common;
+
+use common::code;
+
+const NORMAL: &str = "tests/fixtures/rules/lsof-normal.txt";
+const FD_LEAK: &str = "tests/fixtures/rules/lsof-fd-leak.txt";
+const GC: &str = "tests/fixtures/rules/sample-gc.txt";
+
+/// Written by `--pid N -d -s --redact --bundle` on Linux without perf: the stack
+/// sample failed, the fd probe found CD-FD-001 and the session is orphaned (CD-PRC-002)
+const FAILED_SAMPLE: &str = "tests/fixtures/bundles/failed-sample.tar.gz";
+
+#[test]
+fn exit_code_follows_the_summary() {
+    assert_eq!(code(&["--from-lsof", NORMAL]), 0);
+    assert_eq!(code(&["--from-sample", GC]), 1);
+    assert_eq!(code(&["--from-lsof", FD_LEAK]), 2);
+}
+
+#[test]
+fn fail_on_limits_what_counts() {
+    assert_eq!(code(&["--from-sample", GC, "--fail-on", "high"]), 0);
+    assert_eq!(code(&["--from-sample", GC, "--fail-on", "medium"]), 1);
+    assert_eq!(code(&["--from-lsof", FD_LEAK, "--fail-on", "CD-FD-002"]), 0);
+    assert_eq!(code(&["--from-lsof", FD_LEAK, "--fail-on", "CD-FD-001"]), 2);
+}
+
+#[test]
+fn unusable_input_is_a_probe_failure() {
+    assert_eq!(
+        code(&["--from-lsof", NORMAL, "--fail-on", "CD-NOPE-001"]),
+        3
+    );
+    assert_eq!(code(&["--from-lsof", "tests/fixtures/missing.txt"]), 3);
+    assert_eq!(code(&["--no-such-flag"]), 3);
+}
+
+#[test]
+fn failed_probes_count_only_when_they_could_hide_a_counted_diagnosis() {
+    assert_eq!(code(&["--from-bundle", FAILED_SAMPLE]), 3);
+    assert_eq!(
+        code(&["--from-bundle", FAILED_SAMPLE, "--fail-on", "high"]),
+        3
+    );
+    assert_eq!(
+        code(&["--from-bundle", FAILED_SAMPLE, "--fail-on", "CD-SMP-003"]),
+        3
+    );
+    // The fd probe worked, so its verdict stands
+    assert_eq!(
+        code(&["--from-bundle", FAILED_SAMPLE, "--fail-on", "CD-FD-001"]),
+        2
+    );
+    assert_eq!(
+        code(&["--from-bundle", FAILED_SAMPLE, "--fail-on", "CD-FD-002"]),
+        0
+    );
+    // Listing diagnoses can't be hidden by a failed probe
+    assert_eq!(
+        code(&["--from-bundle", FAILED_SAMPLE, "--fail-on", "CD-PRC-002"]),
+        1
+    );
+}
+
+#[test]
+fn failed_subcommand_probes_and_bundles_exit_3() {
+    // PIDs are capped well below u32::MAX, so this one can't exist
+    assert_eq!(code(&["fds", "4294967294"]), 3);
+    let pid = std::process::id().to_string();
+    assert_eq!(
+        code(&["--pid", &pid, "--bundle", "/nonexistent/dir/bundle.tar.gz"]),
+        3
+    );
+}
@@ -0,0 +1,128 @@
+//! `--redact` hides the same values in text, `--json` and bundle output, and
+//! rewrites values only, so reports still decode when a project, user or host
+//! name equals a field name or an enum value.
+
+mod common;
+
+use common::run;
+use flate2::read::GzDecoder;
+use regex::Regex;
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::io::Read;
+
+fn placeholders(text: &str) -> BTreeSet<String> {
+    let placeholder = Regex::new(r"\[[\w-]+-[0-9a-f]{8}\]").unwrap();
+    placeholder
+        .find_iter(text)
+        .map(|m| m.as_str().to_string())
+        .collect()
+}
+
+/// Replay an lsof fixture with its working directory moved to `cwd`, redacted
+fn redacted_report(fixture: &str, name: &str, cwd: &str) -> Value {
+    let lsof = std::fs::read_to_string(format!(
+        "{}/tests/fixtures/rules/{}",
+        env!("CARGO_MANIFEST_DIR"),
+        fixture
+    ))
+    .unwrap()
+    .replace("/Users/dev/project", cwd);
+    let path = format!("{}/lsof-{}.txt", env!("CARGO_TARGET_TMPDIR"), name);
+    std::fs::write(&path, lsof).unwrap();
+
+    let output = run(&["--from-lsof", &path, "--redact", "--json"]);
+    // Exit codes 1 and 2 report diagnoses; 3 means the report could not be produced
+    assert_ne!(
+        output.status.code(),
+        Some(3),
+        "redacted replay failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    serde_json::from_slice(&output.stdout).expect("report is invalid JSON")
+}
+
+#[test]
+fn project_named_like_a_field_keeps_the_field() {
+    let report = redacted_report("lsof-normal.txt", "field", "/Users/alice/session");
+    let session = &report["processes"][0]["session"];
+    assert!(session.is_object(), "session field lost: {}", report);
+    let cwd = session["cwd"].as_str().unwrap();
+    assert!(
+        !cwd.contains("alice") && !cwd.contains("/session"),
+        "{}",
+        cwd
+    );
+}
+
+#[test]
+fn project_named_like_a_severity_keeps_the_severity() {
+    let report = redacted_report("lsof-fd-leak.txt", "severity", "/Users/alice/high");
+    let cwd = report["processes"][0]["session"]["cwd"].as_str().unwrap();
+    assert!(!cwd.contains("high"), "{}", cwd);
+    let entry = &report["summary"]["critical_issues"][0];
+    assert_eq!(entry["severity"], "high");
+    assert_eq!(entry["id"], "CD-FD-001");
+}
+
+#[test]
+fn text_and_json_hide_the_same_values() {
+    let ps = "tests/fixtures/ps_output_normal.txt";
+    let text = String::from_utf8(run(&["--from-ps", ps, "--redact"]).stdout).unwrap();
+    let json = String::from_utf8(run(&["--from-ps", ps, "--redact", "--json"]).stdout).unwrap();
+    for value in ["/Users/test", "myproject", "another-project"] {
+        assert!(!text.contains(value), "{} left in text output", value);
+        assert!(!json.contains(value), "{} left in JSON output", value);
+    }
+    let (in_text, in_json) = (placeholders(&text), placeholders(&json));
+    assert!(!in_text.is_empty());
+    assert!(
+        in_text.is_subset(&in_json),
+        "text placeholders missing from JSON: {:?}",
+        in_text.difference(&in_json).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn bundle_round_trip_stays_redacted() {
+    let pid = std::process::id().to_string();
+    let plain: Value = serde_json::from_slice(&run(&["--pid", &pid, "--json"]).stdout).unwrap();
+    let hostname = plain["hostname"].as_str().unwrap().to_string();
+    let hostname_word = Regex::new(&format!(r"\b{}\b", regex::escape(&hostname))).unwrap();
+
+    let bundle = format!("{}/redacted.tar.gz", env!("CARGO_TARGET_TMPDIR"));
+    let written = run(&["--pid", &pid, "--redact", "--bundle", &bundle, "--json"]);
+    let redacted: Value = serde_json::from_slice(&written.stdout).unwrap();
+
+    // Every file in the archive is redacted, raw listings included
+    let mut archive = tar::Archive::new(GzDecoder::new(std::fs::File::open(&bundle).unwrap()));
+    let mut files = 0;
+    for entry in archive.entries().unwrap() {
+        let mut entry = entry.unwrap();
+        let name = entry.path().unwrap().display().to_string();
+        let mut content = String::new();
+        entry.read_to_string(&mut content).unwrap();
+        if hostname != "unknown" {
+            assert!(
+                !hostname_word.is_match(&content),
+                "hostname left in {}",
+                name
+            );
+        }
+        assert!(!content.contains("/root/"), "home path left in {}", name);
+        files += 1;
+    }
+    assert!(files >= 4, "only {} files in the bundle", files);
+
+    // The bundle reads back, and the same values got the same placeholders
+    let replayed = run(&["--from-bundle", &bundle, "--json"]);
+    let replayed: Value = serde_json::from_slice(&replayed.stdout).expect("bundle did not load");
+    assert_eq!(replayed["hostname"], redacted["hostname"]);
+    assert_eq!(
+        replayed["processes"][0]["command"],
+        redacted["processes"][0]["command"]
+    );
+    if hostname != "unknown" {
+        assert_ne!(replayed["hostname"], plain["hostname"]);
+    }
+}
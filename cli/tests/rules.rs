@@ -0,0 +1,14 @@
+//! Runs the bundled diagnosis rules against the fixture captures in tests/fixtures/rules.
+
+mod common;
+
+#[test]
+fn bundled_rules_match_fixture_captures() {
+    let output = common::run(&["rules", "test", "tests/fixtures/rules/cases.toml"]);
+
+    assert!(
+        output.status.success(),
+        "rules test failed:\n{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+}
@@ -0,0 +1,201 @@
+//! Guards the `--json` report shape that dashboards and the macOS app decode.
+//!
+//! tests/fixtures/report.schema.json is the schema of the current report version.
+//! Adding fields is fine. Removing a field, making an always-present field optional
+//! or nullable, changing a type or format, or adding an enum value breaks consumers:
+//! bump REPORT_SCHEMA_VERSION and regenerate the snapshot with
+//! `cargo run -- --print-schema > tests/fixtures/report.schema.json`.
+
+mod common;
+
+use common::run;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+
+const SNAPSHOT: &str = "tests/fixtures/report.schema.json";
+
+fn current_schema() -> Value {
+    let output = run(&["--print-schema"]);
+    assert!(output.status.success(), "--print-schema failed");
+    serde_json::from_slice(&output.stdout).expect("--print-schema printed invalid JSON")
+}
+
+fn snapshot_schema() -> Value {
+    let path = format!("{}/{}", env!("CARGO_MANIFEST_DIR"), SNAPSHOT);
+    let text = std::fs::read_to_string(&path).expect("missing schema snapshot");
+    serde_json::from_str(&text).expect("schema snapshot is invalid JSON")
+}
+
+/// Walks the snapshot and current schemas side by side, collecting changes
+/// that would break a consumer written against the snapshot
+struct Compat<'a> {
+    old_root: &'a Value,
+    new_root: &'a Value,
+    seen: HashSet<(String, String)>,
+    breaks: Vec<String>,
+}
+
+impl<'a> Compat<'a> {
+    fn resolve(root: &'a Value, schema: &'a Value) -> (&'a Value, Option<String>) {
+        match schema.get("$ref").and_then(Value::as_str) {
+            Some(r) => {
+                let name = r.trim_start_matches("#/$defs/");
+                (&root["$defs"][name], Some(name.to_string()))
+            }
+            None => (schema, None),
+        }
+    }
+
+    /// Follow a $ref and split off null: (nullable, schema, definition name)
+    fn unwrap(root: &'a Value, schema: &'a Value) -> (bool, &'a Value, Option<String>) {
+        let (schema, name) = Self::resolve(root, schema);
+        let is_null = |s: &Value| s.get("type").and_then(Value::as_str) == Some("null");
+        if let Some(variants) = schema.get("anyOf").and_then(Value::as_array) {
+            let rest: Vec<&Value> = variants.iter().filter(|v| !is_null(v)).collect();
+            if rest.len() == 1 {
+                let (inner, name) = Self::resolve(root, rest[0]);
+                return (rest.len() < variants.len(), inner, name);
+            }
+        }
+        let nullable = match schema.get("type") {
+            Some(Value::Array(types)) => types.iter().any(|t| t == "null"),
+            _ => false,
+        };
+        (nullable, schema, name)
+    }
+
+    fn types(schema: &Value) -> Option<Vec<&str>> {
+        match schema.get("type")? {
+            Value::String(t) => Some(vec![t.as_str()]),
+            Value::Array(types) => Some(
+                types
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .filter(|t| *t != "null")
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+
+    fn compare(&mut self, path: &str, old: &'a Value, new: &'a Value) {
+        let (old_nullable, old, old_name) = Self::unwrap(self.old_root, old);
+        let (new_nullable, new, new_name) = Self::unwrap(self.new_root, new);
+        if new_nullable && !old_nullable {
+            self.breaks.push(format!("{}: can now be null", path));
+        }
+        if let (Some(o), Some(n)) = (old_name, new_name) {
+            if !self.seen.insert((o, n)) {
+                return;
+            }
+        }
+
+        if let (Some(old_types), Some(new_types)) = (Self::types(old), Self::types(new)) {
+            for t in &new_types {
+                // Whole numbers still decode where any number was expected
+                let narrowed = *t == "integer" && old_types.contains(&"number");
+                if !old_types.contains(t) && !narrowed {
+                    self.breaks.push(format!(
+                        "{}: type {} (was {})",
+                        path,
+                        t,
+                        old_types.join(", ")
+                    ));
+                }
+            }
+        }
+        if old.get("format") != new.get("format") && old.get("format").is_some() {
+            self.breaks.push(format!(
+                "{}: format {} (was {})",
+                path, new["format"], old["format"]
+            ));
+        }
+        if let (Some(old_enum), Some(new_enum)) = (
+            old.get("enum").and_then(Value::as_array),
+            new.get("enum").and_then(Value::as_array),
+        ) {
+            for value in new_enum.iter().filter(|v| !old_enum.contains(v)) {
+                self.breaks
+                    .push(format!("{}: new enum value {}", path, value));
+            }
+        }
+
+        if let Some(old_props) = old.get("properties").and_then(Value::as_object) {
+            let required = |s: &Value, name: &str| {
+                s.get("required")
+                    .and_then(Value::as_array)
+                    .is_some_and(|r| r.iter().any(|n| n == name))
+            };
+            for (name, old_prop) in old_props {
+                let field = format!("{}.{}", path, name);
+                let Some(new_prop) = new.get("properties").and_then(|p| p.get(name)) else {
+                    self.breaks.push(format!("{}: removed", field));
+                    continue;
+                };
+                if required(old, name) && !required(new, name) {
+                    self.breaks
+                        .push(format!("{}: no longer always present", field));
+                }
+                self.compare(&field, old_prop, new_prop);
+            }
+        }
+        if let (Some(old_items), Some(new_items)) = (old.get("items"), new.get("items")) {
+            self.compare(&format!("{}[]", path), old_items, new_items);
+        }
+        if let (Some(old_values), Some(new_values)) = (
+            old.get("additionalProperties").filter(|v| v.is_object()),
+            new.get("additionalProperties").filter(|v| v.is_object()),
+        ) {
+            self.compare(&format!("{}{{}}", path), old_values, new_values);
+        }
+    }
+}
+
+#[test]
+fn report_schema_is_backward_compatible() {
+    let (old, new) = (snapshot_schema(), current_schema());
+    // Each definition is compared once, so walk DtraceResult and ProcessReport
+    // before the report itself to name breaks inside them after their own type
+    let named: Vec<(&str, Value)> = ["DtraceResult", "ProcessReport"]
+        .into_iter()
+        .map(|name| (name, json!({ "$ref": format!("#/$defs/{}", name) })))
+        .collect();
+    let mut compat = Compat {
+        old_root: &old,
+        new_root: &new,
+        seen: HashSet::new(),
+        breaks: Vec::new(),
+    };
+    for (name, reference) in &named {
+        compat.compare(name, reference, reference);
+    }
+    compat.compare("DiagnosticReport", &old, &new);
+    let breaks = compat.breaks;
+
+    assert!(
+        breaks.is_empty(),
+        "breaking changes to the report JSON (bump REPORT_SCHEMA_VERSION and regenerate {}):\n  {}",
+        SNAPSHOT,
+        breaks.join("\n  ")
+    );
+    assert_eq!(
+        old["x-schema-version"], new["x-schema-version"],
+        "REPORT_SCHEMA_VERSION changed; regenerate {} with --print-schema",
+        SNAPSHOT
+    );
+}
+
+#[test]
+fn reports_carry_the_schema_version() {
+    let output = run(&[
+        "--from-lsof",
+        "tests/fixtures/rules/lsof-normal.txt",
+        "--json",
+    ]);
+    assert!(output.status.success(), "replay report failed");
+    let report: Value = serde_json::from_slice(&output.stdout).expect("report is invalid JSON");
+    assert_eq!(
+        report["schema_version"],
+        current_schema()["x-schema-version"]
+    );
+}